@@ -0,0 +1,26 @@
+use deno_core::{JsRuntime, RuntimeOptions};
+use std::env;
+use std::path::PathBuf;
+
+const BOOTSTRAP_SRC: &str = include_str!("src/snapshot_bootstrap.js");
+
+/// Builds a V8 startup snapshot containing the handler bootstrap glue (the
+/// `console`/`fetch`/`env` shims handler scripts rely on), so that cost is
+/// paid once at build time instead of on every runtime construction.
+fn main() {
+    println!("cargo:rerun-if-changed=src/snapshot_bootstrap.js");
+
+    let mut js_runtime = JsRuntime::new(RuntimeOptions {
+        will_snapshot: true,
+        ..Default::default()
+    });
+
+    js_runtime
+        .execute_script_static("snapshot_bootstrap.js", BOOTSTRAP_SRC)
+        .expect("bootstrap script must evaluate cleanly to be snapshotted");
+
+    let snapshot = js_runtime.snapshot();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set by cargo"));
+    std::fs::write(out_dir.join("whinator.snapshot"), &*snapshot)
+        .expect("failed to write startup snapshot");
+}
@@ -0,0 +1,298 @@
+use deno_core::anyhow::anyhow;
+use deno_core::{
+    serde_v8, JsRuntime, ModuleLoader, ModuleSource, ModuleSourceFuture, ModuleSpecifier,
+    ModuleType, ResolutionKind, RuntimeOptions,
+};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::{ops, Config, Error, Result};
+
+/// Resolves and loads handler modules off disk, rooted at `handlers_dir`.
+///
+/// Specifiers that would resolve outside of the root are rejected so a
+/// handler can't `import` arbitrary files elsewhere on the host.
+pub struct HandlerModuleLoader {
+    root: PathBuf,
+}
+
+impl HandlerModuleLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        let root = root
+            .canonicalize()
+            .map_err(|source| Error::HandlersDirNotFound { root, source })?;
+        Ok(Self { root })
+    }
+
+    /// Turns a handler path relative to `handlers_dir` into a `file://` module specifier.
+    ///
+    /// Rejects a `relative` that escapes `handlers_dir` (via `..` segments or
+    /// by being absolute), the same as `resolve()` does for a handler's `import`s.
+    pub fn entry_specifier(&self, relative: &str) -> Result<ModuleSpecifier> {
+        let path = self.root.join(relative);
+        let canonical = self.ensure_within_root(&path)?;
+        ModuleSpecifier::from_file_path(&canonical)
+            .map_err(|_| Error::HandlerOutsideRoot(canonical.display().to_string()))
+    }
+
+    fn ensure_within_root(&self, path: &Path) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|source| Error::HandlersDirNotFound {
+                root: path.to_path_buf(),
+                source,
+            })?;
+        if !canonical.starts_with(&self.root) {
+            return Err(Error::HandlerOutsideRoot(canonical.display().to_string()));
+        }
+        Ok(canonical)
+    }
+}
+
+impl ModuleLoader for HandlerModuleLoader {
+    fn resolve(
+        &self,
+        specifier: &str,
+        referrer: &str,
+        _kind: ResolutionKind,
+    ) -> deno_core::anyhow::Result<ModuleSpecifier> {
+        let resolved = deno_core::resolve_import(specifier, referrer)?;
+        if resolved.scheme() != "file" {
+            return Err(anyhow!("only file:// handler modules are supported"));
+        }
+        let path = resolved
+            .to_file_path()
+            .map_err(|_| anyhow!("invalid handler module path: {resolved}"))?;
+        self.ensure_within_root(&path)
+            .map_err(|err| anyhow!(err))?;
+        Ok(resolved)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<&ModuleSpecifier>,
+        _is_dyn_import: bool,
+    ) -> std::pin::Pin<Box<ModuleSourceFuture>> {
+        let module_specifier = module_specifier.clone();
+        Box::pin(async move {
+            let path = module_specifier
+                .to_file_path()
+                .map_err(|_| anyhow!("invalid handler module path: {module_specifier}"))?;
+            let code = tokio::fs::read_to_string(&path).await?;
+            Ok(ModuleSource::new(
+                ModuleType::JavaScript,
+                code.into(),
+                &module_specifier,
+            ))
+        })
+    }
+}
+
+/// A loaded handler module, ready to be invoked repeatedly.
+///
+/// Constructing one pays the full cost of isolate creation plus module
+/// load/evaluate; [`HandlerRuntime::invoke`] is cheap and reuses both across
+/// calls, which is what makes the worker pool worthwhile.
+pub struct HandlerRuntime {
+    js_runtime: JsRuntime,
+    default_export: deno_core::v8::Global<deno_core::v8::Function>,
+    /// Whether `invoke()` should pause for a DevTools session on every call.
+    /// Only ever set when an inspector was actually registered; see `new()`.
+    break_on_invoke: bool,
+    /// How long a single `invoke()` call may wait for the handler's returned
+    /// `Promise` to settle before it's aborted; see `Config::handler_timeout_secs`.
+    invoke_timeout: Duration,
+}
+
+impl HandlerRuntime {
+    /// `inspector` is `(server, name)` for the DevTools session this runtime
+    /// should register itself under, if an inspector is configured.
+    ///
+    /// `Config::inspector_brk` does *not* pause module load here — this
+    /// runtime is long-lived and reused across requests (see the worker
+    /// pool), so pausing once at construction would only ever break on the
+    /// handler module's top-level evaluation, never on an actual webhook.
+    /// Instead `invoke()` arms a fresh break-on-next-statement before each
+    /// call, so a debugger can step through the handler for a real payload.
+    pub async fn new(
+        config: &Config,
+        handler: &str,
+        inspector: Option<(&deno_core::InspectorServer, String)>,
+    ) -> Result<Self> {
+        let loader = HandlerModuleLoader::new(&config.handlers_dir)?;
+        let entry = loader.entry_specifier(handler)?;
+        let startup_snapshot = config
+            .use_snapshot
+            .then(|| deno_core::Snapshot::Static(crate::snapshot::STARTUP_SNAPSHOT));
+        let mut js_runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(loader)),
+            extensions: vec![ops::extension(config)],
+            startup_snapshot,
+            ..Default::default()
+        });
+        if !config.use_snapshot {
+            js_runtime.execute_script_static(
+                "snapshot_bootstrap.js",
+                include_str!("snapshot_bootstrap.js"),
+            )?;
+        }
+
+        let break_on_invoke = config.inspector_brk && inspector.is_some();
+        if let Some((server, name)) = inspector {
+            server.register_inspector(name, &mut js_runtime, false);
+        }
+
+        let mod_id = js_runtime.load_main_module(&entry, None).await?;
+        let eval_receiver = js_runtime.mod_evaluate(mod_id);
+        js_runtime.run_event_loop(false).await?;
+        eval_receiver.await??;
+
+        let default_export = {
+            let module_namespace = js_runtime.get_module_namespace(mod_id)?;
+            let scope = &mut js_runtime.handle_scope();
+            let module_namespace = deno_core::v8::Local::new(scope, module_namespace);
+
+            let default_key =
+                deno_core::v8::String::new(scope, "default").ok_or(Error::JSRuntimePassError)?;
+            let default_export = module_namespace
+                .get(scope, default_key.into())
+                .ok_or(Error::MissingDefaultExport)?;
+            let default_export =
+                deno_core::v8::Local::<deno_core::v8::Function>::try_from(default_export)
+                    .map_err(|_| Error::DefaultExportNotCallable)?;
+            deno_core::v8::Global::new(scope, default_export)
+        };
+
+        Ok(Self {
+            js_runtime,
+            default_export,
+            break_on_invoke,
+            invoke_timeout: Duration::from_secs(config.handler_timeout_secs.max(1)),
+        })
+    }
+
+    /// Calls the handler's default export with `(body, event)` and waits for its
+    /// result, including awaiting a returned `Promise` if the handler is async.
+    /// `event` is the matched webhook event name (e.g. `push`), if any.
+    ///
+    /// Bounded by `invoke_timeout`: a handler whose `Promise` never settles
+    /// (a forgotten resolver, an event that never fires) would otherwise spin
+    /// `run_event_loop` forever and wedge the worker that's running it.
+    pub async fn invoke(
+        &mut self,
+        body: serde_json::Value,
+        event: Option<String>,
+    ) -> Result<serde_json::Value> {
+        match tokio::time::timeout(self.invoke_timeout, self.invoke_uncapped(body, event)).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::HandlerTimedOut(self.invoke_timeout)),
+        }
+    }
+
+    async fn invoke_uncapped(
+        &mut self,
+        body: serde_json::Value,
+        event: Option<String>,
+    ) -> Result<serde_json::Value> {
+        if self.break_on_invoke {
+            self.js_runtime
+                .inspector()
+                .borrow_mut()
+                .wait_for_session_and_break_on_next_statement();
+        }
+
+        let result = {
+            let scope = &mut self.js_runtime.handle_scope();
+            let default_export = deno_core::v8::Local::new(scope, self.default_export.clone());
+
+            let body_value = serde_v8::to_v8(scope, body)?;
+            let event_value = serde_v8::to_v8(scope, event)?;
+            let undefined = deno_core::v8::undefined(scope).into();
+            let result = default_export
+                .call(scope, undefined, &[body_value, event_value])
+                .ok_or(Error::JSRuntimePassError)?;
+            deno_core::v8::Global::new(scope, result)
+        };
+
+        // Handlers commonly return a `Promise` (e.g. anything that awaits `op_fetch`), so
+        // pump the event loop until it settles rather than reading it immediately.
+        let settled = loop {
+            let poll_again = {
+                let scope = &mut self.js_runtime.handle_scope();
+                let local = deno_core::v8::Local::new(scope, result.clone());
+                match deno_core::v8::Local::<deno_core::v8::Promise>::try_from(local) {
+                    Ok(promise) => match promise.state() {
+                        deno_core::v8::PromiseState::Pending => true,
+                        deno_core::v8::PromiseState::Fulfilled => {
+                            break Ok(deno_core::v8::Global::new(scope, promise.result(scope)))
+                        }
+                        deno_core::v8::PromiseState::Rejected => {
+                            break Err(deno_core::v8::Global::new(scope, promise.result(scope)))
+                        }
+                    },
+                    Err(_) => break Ok(result.clone()),
+                }
+            };
+            if poll_again {
+                self.js_runtime.run_event_loop(false).await?;
+                // With no ops pending, `run_event_loop` returns at once; yield so the
+                // `invoke_timeout` wrapping this future gets polled and can fire.
+                tokio::task::yield_now().await;
+            }
+        };
+
+        let scope = &mut self.js_runtime.handle_scope();
+        match settled {
+            Ok(global) => {
+                let local = deno_core::v8::Local::new(scope, global);
+                Ok(serde_v8::from_v8::<serde_json::Value>(scope, local)?)
+            }
+            Err(global) => {
+                let local = deno_core::v8::Local::new(scope, global);
+                Err(Error::HandlerRejected(local.to_rust_string_lossy(scope)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn unique_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("whinator-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn entry_specifier_accepts_file_within_root() {
+        let root = unique_dir("entry-ok");
+        fs::write(root.join("index.js"), "export default () => {};").unwrap();
+
+        let loader = HandlerModuleLoader::new(&root).unwrap();
+        assert!(loader.entry_specifier("index.js").is_ok());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn entry_specifier_rejects_path_traversal() {
+        let base = unique_dir("entry-escape");
+        let root = base.join("handlers");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(base.join("evil.js"), "export default () => {};").unwrap();
+
+        let loader = HandlerModuleLoader::new(&root).unwrap();
+        let result = loader.entry_specifier("../evil.js");
+
+        assert!(matches!(result, Err(Error::HandlerOutsideRoot(_))));
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
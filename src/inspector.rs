@@ -0,0 +1,13 @@
+use deno_core::InspectorServer;
+use std::net::SocketAddr;
+
+/// Builds the shared Chrome DevTools Protocol inspector server, if configured.
+///
+/// `deno_core`'s `InspectorServer` is itself a small hyper-based websocket
+/// server, so this doesn't pull in a new dependency. One server is shared
+/// across every pool worker; each worker's `HandlerRuntime` registers its own
+/// isolate under a distinct name (see [`crate::pool`]) so DevTools can tell
+/// them apart and attach to whichever worker handled a given request.
+pub fn maybe_server(addr: Option<SocketAddr>) -> Option<InspectorServer> {
+    addr.map(|addr| InspectorServer::new(addr, "whinator"))
+}
@@ -1,12 +1,31 @@
-use axum::{http::StatusCode, routing::post, Json, Router};
+use axum::{
+    body::Bytes,
+    extract::{MatchedPath, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
 use config::File;
-use deno_core::{serde_v8, JsRuntime};
 use miette::Diagnostic;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::net::AddrParseError;
+use std::path::PathBuf;
+use std::sync::Arc;
 use thiserror::Error;
 use tracing::info;
 
+mod handler;
+mod inspector;
+mod ops;
+mod pool;
+mod routing;
+mod signature;
+mod snapshot;
+
+use pool::Pool;
+use routing::Route;
+
 #[derive(Debug, Error, Diagnostic)]
 pub enum Error {
     #[error(transparent)]
@@ -22,28 +41,169 @@ pub enum Error {
 
     #[error("body could not be passed to js runtime")]
     JSRuntimePassError,
+
+    #[error("handlers_dir {root} does not exist or is not readable")]
+    HandlersDirNotFound {
+        root: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("handler module {0} resolves outside of handlers_dir")]
+    HandlerOutsideRoot(String),
+
+    #[error("handler module has no default export")]
+    MissingDefaultExport,
+
+    #[error("default export of handler module is not callable")]
+    DefaultExportNotCallable,
+
+    #[error("missing webhook signature header")]
+    MissingSignature,
+
+    #[error("webhook signature does not match")]
+    InvalidSignature,
+
+    #[error("request body is not valid JSON")]
+    InvalidBody(#[from] serde_json::Error),
+
+    #[error("handler rejected its promise: {0}")]
+    HandlerRejected(String),
+
+    #[error("handler worker pool is no longer accepting jobs")]
+    WorkerPoolClosed,
+
+    #[error("failed to start handler worker's Tokio runtime")]
+    WorkerRuntime(#[source] std::io::Error),
+
+    #[error("handler worker could not start: {0}")]
+    WorkerUnavailable(String),
+
+    #[error("handler did not settle its promise within {0:?}")]
+    HandlerTimedOut(std::time::Duration),
+
+    #[error("no route configured for path {path} and event {event:?}")]
+    NoRouteMatched { path: String, event: Option<String> },
 }
 
 pub type Result<T> = miette::Result<T, Error>;
 
+/// How an incoming webhook's authenticity is verified.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureMode {
+    /// GitHub-style `X-Hub-Signature-256: sha256=<hex>` HMAC over the raw body.
+    GithubSha256,
+    /// GitLab's plain shared-token style `X-Gitlab-Token` header.
+    GitlabToken,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
     listen: String,
+    /// Root directory handler modules (and anything they `import`) are resolved from.
+    handlers_dir: PathBuf,
+    /// Rules mapping a request path and/or webhook event type to a handler module.
+    /// Defaults to a single catch-all rule on `/` running `index.js`.
+    #[serde(default)]
+    routes: Vec<Route>,
+    /// Shared secret used to verify incoming webhooks. Empty disables verification.
+    #[serde(default)]
+    secret: String,
+    /// Which signature scheme `secret` is checked against.
+    signature_mode: SignatureMode,
+    /// Hosts `op_fetch` is allowed to reach from inside a handler script.
+    #[serde(default)]
+    fetch_allowlist: Vec<String>,
+    /// Environment variable names `op_env` is allowed to read from inside a handler script.
+    #[serde(default)]
+    env_allowlist: Vec<String>,
+    /// Number of pre-warmed handler workers to run.
+    workers: usize,
+    /// Construct handler runtimes from the precompiled V8 startup snapshot
+    /// instead of evaluating the bootstrap glue from source on every worker.
+    #[serde(default)]
+    use_snapshot: bool,
+    /// When set, attach a Chrome DevTools Protocol inspector on this address
+    /// so handler scripts can be debugged.
+    #[serde(default)]
+    inspector_addr: Option<std::net::SocketAddr>,
+    /// Before each handler invocation, wait for a DevTools session and break
+    /// on the handler's first statement (`--inspect-brk`-style). Only takes
+    /// effect when `inspector_addr` is set.
+    #[serde(default)]
+    inspector_brk: bool,
+    /// How long `HandlerRuntime::invoke` waits for a handler's returned
+    /// `Promise` to settle before failing the call. Guards against a handler
+    /// script that never resolves/rejects wedging a worker forever.
+    #[serde(default = "default_handler_timeout_secs")]
+    handler_timeout_secs: u64,
+}
+
+fn default_handler_timeout_secs() -> u64 {
+    30
 }
 
 pub async fn load_config() -> Result<Config> {
     let builder = config::Config::builder()
         .set_default("listen", "0.0.0.0:3000")?
+        .set_default("handlers_dir", "./handlers")?
+        .set_default("secret", "")?
+        .set_default("signature_mode", "github_sha256")?
+        .set_default("fetch_allowlist", Vec::<String>::new())?
+        .set_default("env_allowlist", Vec::<String>::new())?
+        .set_default("workers", 4)?
+        .set_default("use_snapshot", false)?
+        .set_default("handler_timeout_secs", 30)?
         .add_source(File::with_name("/etc/whinator.yaml").required(false));
     let cfg = builder.build()?;
 
     tracing::debug!("Loaded Configuration");
 
-    Ok(cfg.try_deserialize()?)
+    let mut config: Config = cfg.try_deserialize()?;
+    if config.routes.is_empty() {
+        config.routes.push(Route {
+            path: "/".to_string(),
+            event: None,
+            handler: "index.js".to_string(),
+        });
+    }
+    Ok(config)
+}
+
+#[derive(Clone)]
+struct AppState {
+    config: Arc<Config>,
+    /// One pool per distinct handler module referenced across `config.routes`.
+    pools: Arc<HashMap<String, Pool>>,
 }
 
 pub async fn listen(config: Config) -> Result<()> {
-    let app = Router::new().route("/", post(handle_webhook));
+    let config = Arc::new(config);
+    let inspector = inspector::maybe_server(config.inspector_addr).map(Arc::new);
+
+    let mut pools = HashMap::new();
+    for route in &config.routes {
+        pools.entry(route.handler.clone()).or_insert_with(|| {
+            Pool::spawn(
+                config.clone(),
+                Arc::from(route.handler.as_str()),
+                inspector.clone(),
+            )
+        });
+    }
+
+    let state = AppState {
+        config: config.clone(),
+        pools: Arc::new(pools),
+    };
+
+    let distinct_paths: HashSet<&str> = config.routes.iter().map(|route| route.path.as_str()).collect();
+    let mut app = Router::new();
+    for path in distinct_paths {
+        app = app.route(path, post(handle_webhook));
+    }
+    let app = app.with_state(state);
 
     info!("Listening on {0}", &config.listen);
     // run it with hyper on localhost:3000
@@ -53,9 +213,46 @@ pub async fn listen(config: Config) -> Result<()> {
     Ok(())
 }
 
-async fn handle_webhook(Json(body): Json<serde_json::Value>) -> (StatusCode, String) {
-    match real_handler(body).await {
-        Ok(_) => (StatusCode::OK, String::new()),
+async fn handle_webhook(
+    State(state): State<AppState>,
+    matched_path: MatchedPath,
+    headers: HeaderMap,
+    raw_body: Bytes,
+) -> (StatusCode, String) {
+    if let Err(err) = signature::verify(&state.config, &headers, &raw_body) {
+        tracing::warn!("Rejecting webhook: {}", err);
+        return (StatusCode::UNAUTHORIZED, err.to_string());
+    }
+
+    let event = headers
+        .get("X-GitHub-Event")
+        .or_else(|| headers.get("X-Gitlab-Event"))
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let Some(route) = routing::resolve(&state.config.routes, matched_path.as_str(), event.as_deref())
+    else {
+        let err = Error::NoRouteMatched {
+            path: matched_path.as_str().to_string(),
+            event,
+        };
+        tracing::warn!("{}", err);
+        return (StatusCode::NOT_FOUND, err.to_string());
+    };
+
+    let body: serde_json::Value = match serde_json::from_slice(&raw_body).map_err(Error::from) {
+        Ok(body) => body,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()),
+    };
+
+    // `route.handler` always has a pool: `listen` builds one per distinct handler in `config.routes`.
+    let pool = &state.pools[&route.handler];
+
+    match pool.dispatch(body, event).await {
+        Ok(value) => {
+            info!("Result of Javascript evaliation: {}", value);
+            (StatusCode::OK, String::new())
+        }
         Err(err) => {
             tracing::error!("Failed to handle webhook: {}", err);
             (
@@ -65,34 +262,3 @@ async fn handle_webhook(Json(body): Json<serde_json::Value>) -> (StatusCode, Str
         }
     }
 }
-
-async fn real_handler(body: serde_json::Value) -> Result<()> {
-    info!("Starting Deno Runtime");
-    let mut js_runtime = JsRuntime::new(Default::default());
-    {
-        let mut scope = js_runtime.handle_scope();
-        let variable_context = scope.get_current_context();
-        let global = variable_context.global(&mut scope);
-
-        let body_value = serde_v8::to_v8(&mut scope, body)?;
-
-        let body_key_str =
-            deno_core::v8::String::new(&mut scope, "body").ok_or(Error::JSRuntimePassError)?;
-        let _ = global
-            .set(&mut scope, body_key_str.into(), body_value)
-            .ok_or(Error::JSRuntimePassError)?;
-    }
-
-    let result = js_runtime.execute_script_static("handler", "body.pull_request.url;")?;
-    let str = {
-        let scope = &mut js_runtime.handle_scope();
-        let local = deno_core::v8::Local::new(scope, result);
-        // Deserialize a `v8` object into a Rust type using `serde_v8`,
-        // in this case deserialize to a JSON `Value`.
-        let deserialized_value = serde_v8::from_v8::<serde_json::Value>(scope, local)?;
-        deserialized_value
-    };
-    info!("Result of Javascript evaliation: {}", str);
-
-    Ok(())
-}
@@ -0,0 +1,145 @@
+use deno_core::{anyhow::anyhow, op, Extension, OpState};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::Config;
+
+/// Hosts an `op_fetch` call is allowed to reach, taken from `Config::fetch_allowlist`.
+struct FetchAllowlist(Vec<String>);
+
+/// Environment variable names `op_env` is allowed to read, taken from `Config::env_allowlist`.
+struct EnvAllowlist(Vec<String>);
+
+/// Builds the `whinator` extension that gives handler scripts access to host capabilities.
+pub fn extension(config: &Config) -> Extension {
+    let fetch_allowlist = FetchAllowlist(config.fetch_allowlist.clone());
+    let env_allowlist = EnvAllowlist(config.env_allowlist.clone());
+
+    Extension::builder("whinator")
+        .ops(vec![op_fetch::decl(), op_log::decl(), op_env::decl()])
+        .state(move |state| {
+            state.put(FetchAllowlist(fetch_allowlist.0.clone()));
+            state.put(EnvAllowlist(env_allowlist.0.clone()));
+        })
+        .build()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FetchInit {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FetchResponse {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+fn host_is_allowed(allowlist: &[String], host: &str) -> bool {
+    allowlist.iter().any(|allowed| allowed == host)
+}
+
+#[op]
+async fn op_fetch(
+    state: Rc<RefCell<OpState>>,
+    url: String,
+    init: Option<FetchInit>,
+) -> deno_core::anyhow::Result<FetchResponse> {
+    let parsed = reqwest::Url::parse(&url)?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| anyhow!("url {url} has no host"))?;
+
+    {
+        let state = state.borrow();
+        let allowlist = state.borrow::<FetchAllowlist>();
+        if !host_is_allowed(&allowlist.0, host) {
+            return Err(anyhow!("host {host} is not in the fetch allow-list"));
+        }
+    }
+
+    let init = init.unwrap_or(FetchInit {
+        method: None,
+        headers: HashMap::new(),
+        body: None,
+    });
+    let method: reqwest::Method = init
+        .method
+        .as_deref()
+        .unwrap_or("GET")
+        .parse()
+        .map_err(|_| anyhow!("invalid HTTP method"))?;
+
+    // reqwest follows redirects by default, which would let a handler reach an
+    // allow-listed host that 30x-redirects into somewhere it isn't allowed
+    // (e.g. an internal/metadata address). Re-check the allow-list on every hop.
+    let redirect_allowlist = {
+        let state = state.borrow();
+        state.borrow::<FetchAllowlist>().0.clone()
+    };
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            match attempt.url().host_str() {
+                Some(host) if host_is_allowed(&redirect_allowlist, host) => attempt.follow(),
+                _ => attempt.stop(),
+            }
+        }))
+        .build()?;
+    let mut request = client.request(method, parsed);
+    for (name, value) in init.headers {
+        request = request.header(name, value);
+    }
+    if let Some(body) = init.body {
+        request = request.body(body);
+    }
+
+    let response = request.send().await?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.to_string(), value.to_string()))
+        })
+        .collect();
+    let body = response.text().await?;
+
+    Ok(FetchResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+#[op]
+fn op_log(level: String, message: String) {
+    match level.as_str() {
+        "error" => tracing::error!(target: "handler", "{message}"),
+        "warn" => tracing::warn!(target: "handler", "{message}"),
+        "debug" => tracing::debug!(target: "handler", "{message}"),
+        _ => tracing::info!(target: "handler", "{message}"),
+    }
+}
+
+#[op]
+fn op_env(state: &mut OpState, name: String) -> Option<String> {
+    let allowlist = state.borrow::<EnvAllowlist>();
+    if !allowlist.0.iter().any(|allowed| allowed == &name) {
+        tracing::warn!("handler requested non-allow-listed env var {name}");
+        return None;
+    }
+    std::env::var(&name).ok()
+}
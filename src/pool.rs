@@ -0,0 +1,148 @@
+use deno_core::InspectorServer;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::handler::HandlerRuntime;
+use crate::{Config, Error, Result};
+
+/// Backoff before a worker's first respawn attempt after a crash.
+const INITIAL_RESPAWN_BACKOFF: Duration = Duration::from_millis(200);
+/// Cap on the respawn backoff, reached after repeated consecutive crashes.
+const MAX_RESPAWN_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A parsed webhook body plus a channel to deliver the handler's result.
+struct Job {
+    body: serde_json::Value,
+    event: Option<String>,
+    reply: oneshot::Sender<Result<serde_json::Value>>,
+}
+
+/// A pool of pre-warmed handler workers, all running the same handler module.
+///
+/// V8 isolates are `!Send`, so each worker owns its `JsRuntime` on a
+/// dedicated OS thread with its own current-thread Tokio runtime. Jobs are
+/// handed over on an `mpsc` channel; this amortizes isolate startup and
+/// module evaluation across requests instead of paying for it every time.
+#[derive(Clone)]
+pub struct Pool {
+    sender: mpsc::Sender<Job>,
+}
+
+impl Pool {
+    /// Spawns `config.workers` worker threads, each loading `handler`.
+    ///
+    /// If `inspector` is set, each worker registers its isolate with it under
+    /// `{handler}#{worker id}` so DevTools can attach to a specific worker.
+    pub fn spawn(config: Arc<Config>, handler: Arc<str>, inspector: Option<Arc<InspectorServer>>) -> Self {
+        let worker_count = config.workers.max(1);
+        let (sender, receiver) = mpsc::channel(worker_count * 8);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for id in 0..worker_count {
+            spawn_worker(
+                id,
+                config.clone(),
+                handler.clone(),
+                inspector.clone(),
+                receiver.clone(),
+            );
+        }
+
+        Self { sender }
+    }
+
+    /// Sends `body`/`event` to the next available worker and awaits its result.
+    pub async fn dispatch(
+        &self,
+        body: serde_json::Value,
+        event: Option<String>,
+    ) -> Result<serde_json::Value> {
+        let (reply, response) = oneshot::channel();
+        self.sender
+            .send(Job { body, event, reply })
+            .await
+            .map_err(|_| Error::WorkerPoolClosed)?;
+        response.await.map_err(|_| Error::WorkerPoolClosed)?
+    }
+}
+
+fn spawn_worker(
+    id: usize,
+    config: Arc<Config>,
+    handler: Arc<str>,
+    inspector: Option<Arc<InspectorServer>>,
+    receiver: Arc<Mutex<mpsc::Receiver<Job>>>,
+) {
+    std::thread::Builder::new()
+        .name(format!("handler-worker-{handler}-{id}"))
+        .spawn(move || {
+            let mut backoff = INITIAL_RESPAWN_BACKOFF;
+            loop {
+                tracing::info!("starting handler worker {id} for {handler}");
+                match run_worker(&config, &handler, id, inspector.as_deref(), &receiver) {
+                    Ok(()) => break,
+                    Err(err) => {
+                        tracing::error!(
+                            "handler worker {id} for {handler} crashed, restarting in {backoff:?}: {err}"
+                        );
+                        std::thread::sleep(backoff);
+                        backoff = (backoff * 2).min(MAX_RESPAWN_BACKOFF);
+                    }
+                }
+            }
+        })
+        .expect("failed to spawn handler worker thread");
+}
+
+/// Runs one worker's event loop until the pool is shut down. Returns an error
+/// only when the worker cannot start (its Tokio runtime or `HandlerRuntime`
+/// fails to build), in which case the caller restarts it with a fresh isolate.
+/// Errors from individual invocations are sent back on the job's reply
+/// channel and the worker keeps serving jobs with the same isolate.
+fn run_worker(
+    config: &Config,
+    handler: &str,
+    id: usize,
+    inspector: Option<&InspectorServer>,
+    receiver: &Arc<Mutex<mpsc::Receiver<Job>>>,
+) -> Result<()> {
+    let tokio_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(Error::WorkerRuntime)?;
+
+    tokio_runtime.block_on(async {
+        let session = inspector.map(|server| (server, format!("{handler}#{id}")));
+        let mut handler_runtime = match HandlerRuntime::new(config, handler, session).await {
+            Ok(handler_runtime) => handler_runtime,
+            Err(err) => {
+                // This worker can't serve jobs at all, and every other worker for
+                // `handler` was built from the same config, so they're about to fail
+                // identically rather than pick up the slack. Fail whatever's already
+                // queued instead of letting it hang on `Pool::dispatch` forever.
+                fail_queued_jobs(receiver, &err).await;
+                return Err(err);
+            }
+        };
+        loop {
+            let job = receiver.lock().await.recv().await;
+            let Some(job) = job else {
+                break;
+            };
+            let result = handler_runtime.invoke(job.body, job.event).await;
+            let _ = job.reply.send(result);
+        }
+        Ok(())
+    })
+}
+
+/// Drains any jobs already queued and fails them with `err` rather than
+/// leaving their `Pool::dispatch` caller waiting on a worker that will never
+/// come back to service the channel.
+async fn fail_queued_jobs(receiver: &Arc<Mutex<mpsc::Receiver<Job>>>, err: &Error) {
+    let mut receiver = receiver.lock().await;
+    while let Ok(job) = receiver.try_recv() {
+        let _ = job.reply.send(Err(Error::WorkerUnavailable(err.to_string())));
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+
+fn default_route_path() -> String {
+    "/".to_string()
+}
+
+/// A rule mapping an incoming webhook's path and/or event type to a handler module.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Route {
+    /// Request path this rule matches, e.g. `/` or `/github`.
+    #[serde(default = "default_route_path")]
+    pub path: String,
+    /// Event type to match, taken from GitHub's `X-GitHub-Event` or GitLab's
+    /// `X-Gitlab-Event` header. `None` matches any event on `path`.
+    #[serde(default)]
+    pub event: Option<String>,
+    /// Handler module to invoke for this rule, relative to `handlers_dir`.
+    pub handler: String,
+}
+
+/// Picks the most specific route for `path`/`event`: an exact event match
+/// wins, falling back to a route on the same path with no event constraint.
+pub fn resolve<'a>(routes: &'a [Route], path: &str, event: Option<&str>) -> Option<&'a Route> {
+    routes
+        .iter()
+        .filter(|route| route.path == path)
+        .find(|route| route.event.as_deref() == event)
+        .or_else(|| {
+            routes
+                .iter()
+                .filter(|route| route.path == path)
+                .find(|route| route.event.is_none())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(path: &str, event: Option<&str>, handler: &str) -> Route {
+        Route {
+            path: path.to_string(),
+            event: event.map(str::to_string),
+            handler: handler.to_string(),
+        }
+    }
+
+    #[test]
+    fn exact_event_match_wins_over_catch_all() {
+        let routes = vec![
+            route("/", None, "catch_all.js"),
+            route("/", Some("push"), "push.js"),
+        ];
+
+        let matched = resolve(&routes, "/", Some("push")).unwrap();
+        assert_eq!(matched.handler, "push.js");
+    }
+
+    #[test]
+    fn falls_back_to_catch_all_for_unmatched_event() {
+        let routes = vec![
+            route("/", None, "catch_all.js"),
+            route("/", Some("push"), "push.js"),
+        ];
+
+        let matched = resolve(&routes, "/", Some("issues")).unwrap();
+        assert_eq!(matched.handler, "catch_all.js");
+    }
+
+    #[test]
+    fn falls_back_to_catch_all_when_no_event_header() {
+        let routes = vec![
+            route("/", None, "catch_all.js"),
+            route("/", Some("push"), "push.js"),
+        ];
+
+        let matched = resolve(&routes, "/", None).unwrap();
+        assert_eq!(matched.handler, "catch_all.js");
+    }
+
+    #[test]
+    fn no_match_for_unknown_path() {
+        let routes = vec![route("/", None, "catch_all.js")];
+
+        assert!(resolve(&routes, "/other", None).is_none());
+    }
+
+    #[test]
+    fn no_match_when_only_other_events_configured_for_path() {
+        let routes = vec![route("/", Some("push"), "push.js")];
+
+        assert!(resolve(&routes, "/", Some("issues")).is_none());
+    }
+}
@@ -0,0 +1,151 @@
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::{Config, Error, Result, SignatureMode};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const GITHUB_SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+const GITLAB_TOKEN_HEADER: &str = "X-Gitlab-Token";
+
+/// Verifies the raw request body against the configured webhook secret.
+///
+/// Must run before the body is deserialized, since signatures are computed
+/// over the exact bytes the sender transmitted.
+pub fn verify(config: &Config, headers: &HeaderMap, raw_body: &[u8]) -> Result<()> {
+    if config.secret.is_empty() {
+        tracing::warn!("no webhook secret configured; skipping signature verification");
+        return Ok(());
+    }
+
+    match config.signature_mode {
+        SignatureMode::GithubSha256 => verify_github(config, headers, raw_body),
+        SignatureMode::GitlabToken => verify_gitlab(config, headers),
+    }
+}
+
+fn verify_github(config: &Config, headers: &HeaderMap, raw_body: &[u8]) -> Result<()> {
+    let header = headers
+        .get(GITHUB_SIGNATURE_HEADER)
+        .ok_or(Error::MissingSignature)?
+        .to_str()
+        .map_err(|_| Error::InvalidSignature)?;
+    let digest_hex = header.strip_prefix("sha256=").ok_or(Error::InvalidSignature)?;
+    let expected = hex::decode(digest_hex).map_err(|_| Error::InvalidSignature)?;
+
+    let mut mac = HmacSha256::new_from_slice(config.secret.as_bytes())
+        .expect("HMAC accepts a key of any length");
+    mac.update(raw_body);
+    mac.verify_slice(&expected).map_err(|_| Error::InvalidSignature)
+}
+
+fn verify_gitlab(config: &Config, headers: &HeaderMap) -> Result<()> {
+    let header = headers
+        .get(GITLAB_TOKEN_HEADER)
+        .ok_or(Error::MissingSignature)?
+        .to_str()
+        .map_err(|_| Error::InvalidSignature)?;
+
+    if header.as_bytes().ct_eq(config.secret.as_bytes()).into() {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(secret: &str, signature_mode: SignatureMode) -> Config {
+        Config {
+            listen: "0.0.0.0:3000".to_string(),
+            handlers_dir: "./handlers".into(),
+            routes: Vec::new(),
+            secret: secret.to_string(),
+            signature_mode,
+            fetch_allowlist: Vec::new(),
+            env_allowlist: Vec::new(),
+            workers: 1,
+            use_snapshot: false,
+            inspector_addr: None,
+            inspector_brk: false,
+            handler_timeout_secs: 30,
+        }
+    }
+
+    fn github_headers(secret: &str, body: &[u8]) -> HeaderMap {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        let digest = hex::encode(mac.finalize().into_bytes());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            GITHUB_SIGNATURE_HEADER,
+            format!("sha256={digest}").parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn github_valid_signature_is_accepted() {
+        let config = test_config("s3cr3t", SignatureMode::GithubSha256);
+        let body = b"{\"ok\":true}";
+        let headers = github_headers("s3cr3t", body);
+
+        assert!(verify(&config, &headers, body).is_ok());
+    }
+
+    #[test]
+    fn github_mismatched_signature_is_rejected() {
+        let config = test_config("s3cr3t", SignatureMode::GithubSha256);
+        let body = b"{\"ok\":true}";
+        let headers = github_headers("wrong-secret", body);
+
+        assert!(matches!(
+            verify(&config, &headers, body),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn github_missing_signature_header_is_rejected() {
+        let config = test_config("s3cr3t", SignatureMode::GithubSha256);
+        let body = b"{\"ok\":true}";
+
+        assert!(matches!(
+            verify(&config, &HeaderMap::new(), body),
+            Err(Error::MissingSignature)
+        ));
+    }
+
+    #[test]
+    fn gitlab_matching_token_is_accepted() {
+        let config = test_config("s3cr3t", SignatureMode::GitlabToken);
+        let mut headers = HeaderMap::new();
+        headers.insert(GITLAB_TOKEN_HEADER, "s3cr3t".parse().unwrap());
+
+        assert!(verify(&config, &headers, b"{}").is_ok());
+    }
+
+    #[test]
+    fn gitlab_mismatched_token_is_rejected() {
+        let config = test_config("s3cr3t", SignatureMode::GitlabToken);
+        let mut headers = HeaderMap::new();
+        headers.insert(GITLAB_TOKEN_HEADER, "not-the-secret".parse().unwrap());
+
+        assert!(matches!(
+            verify(&config, &headers, b"{}"),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn empty_secret_disables_verification() {
+        let config = test_config("", SignatureMode::GithubSha256);
+
+        assert!(verify(&config, &HeaderMap::new(), b"anything").is_ok());
+    }
+}
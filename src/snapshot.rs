@@ -0,0 +1,9 @@
+/// The V8 startup snapshot produced by `build.rs` from `snapshot_bootstrap.js`.
+///
+/// Every worker in the pool ([`crate::pool::Pool`]) constructs its own
+/// [`crate::handler::HandlerRuntime`] independently, so each deserializes
+/// this same blob once at worker startup rather than re-running the
+/// bootstrap script from source. It's all-or-nothing across the pool: there
+/// is no per-worker snapshot toggle, only the global `Config::use_snapshot`.
+pub static STARTUP_SNAPSHOT: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/whinator.snapshot"));